@@ -2,19 +2,40 @@ use super::{Callback, Component, NodeRef, Renderable};
 use crate::scheduler::{scheduler, ComponentRunnableType, Runnable, Shared};
 use crate::virtual_dom::{VDiff, VNode};
 use cfg_if::cfg_if;
+use futures::future::{AbortHandle, Abortable};
 use std::any::{Any, TypeId};
-use std::cell::{Ref, RefCell};
+use std::cell::{Cell, Ref, RefCell};
+use std::collections::HashMap;
 use std::fmt;
+use std::future::Future;
 use std::ops::Deref;
+use std::pin::Pin;
 use std::rc::Rc;
 cfg_if! {
     if #[cfg(feature = "std_web")] {
         use stdweb::web::Element;
+        use stdweb::spawn_local;
     } else if #[cfg(feature = "web_sys")] {
         use web_sys::Element;
+        use wasm_bindgen_futures::spawn_local;
     }
 }
 
+/// Describes what a component wants to happen after [`Component::update`].
+///
+/// Returning [`UpdateAction::None`] or [`UpdateAction::Render`] is equivalent to
+/// the `false`/`true` a plain `update` would yield. [`UpdateAction::Defer`]
+/// additionally spawns a future on the local executor; once it resolves its
+/// output is sent back to the component as a follow-up message.
+pub enum UpdateAction<COMP: Component> {
+    /// Do not re-render.
+    None,
+    /// Re-render the component.
+    Render,
+    /// Spawn a future and feed its output back as a message once it resolves.
+    Defer(Pin<Box<dyn Future<Output = COMP::Message>>>),
+}
+
 /// Updates for a `Component` instance. Used by scope sender.
 pub(crate) enum ComponentUpdate<COMP: Component> {
     /// Force update
@@ -27,18 +48,31 @@ pub(crate) enum ComponentUpdate<COMP: Component> {
     Properties(COMP::Properties, NodeRef),
 }
 
+/// A type-keyed map of context values shared by a scope with its descendants.
+type ContextMap = Rc<RefCell<HashMap<TypeId, Rc<dyn Any>>>>;
+
+/// Registry of in-flight [`Scope::spawn`] tasks, keyed by a per-scope id so
+/// finished tasks can remove their own handle.
+type TaskRegistry = Rc<RefCell<HashMap<u32, AbortHandle>>>;
+
 /// Untyped scope used for accessing parent scope
 #[derive(Debug, Clone)]
 pub struct AnyScope {
     pub(crate) type_id: TypeId,
     pub(crate) parent: Option<Rc<AnyScope>>,
     pub(crate) state: Rc<dyn Any>,
+    pub(crate) contexts: ContextMap,
+    pub(crate) tasks: TaskRegistry,
+    pub(crate) next_task_id: Rc<Cell<u32>>,
 }
 
 impl<COMP: Component> From<Scope<COMP>> for AnyScope {
     fn from(scope: Scope<COMP>) -> Self {
         AnyScope {
             type_id: TypeId::of::<COMP>(),
+            contexts: scope.contexts.clone(),
+            tasks: scope.tasks.clone(),
+            next_task_id: scope.next_task_id.clone(),
             parent: scope.parent,
             state: Rc::new(scope.state),
         }
@@ -56,15 +90,31 @@ impl AnyScope {
         &self.type_id
     }
 
+    /// Walks up the parent chain and returns the nearest ancestor's value of
+    /// type `T` provided via [`Scope::provide_context`], if any.
+    pub fn consume_context<T: 'static>(&self) -> Option<Rc<T>> {
+        let mut scope = self.get_parent();
+        while let Some(current) = scope {
+            if let Some(value) = current.contexts.borrow().get(&TypeId::of::<T>()) {
+                return value.clone().downcast::<T>().ok();
+            }
+            scope = current.get_parent();
+        }
+        None
+    }
+
     /// Attempts to downcast into a typed scope
     pub fn downcast<COMP: Component>(self) -> Scope<COMP> {
         Scope {
-            parent: self.parent,
             state: self
                 .state
                 .downcast_ref::<Shared<Option<ComponentState<COMP>>>>()
                 .expect("unexpected component type")
                 .clone(),
+            contexts: self.contexts,
+            tasks: self.tasks,
+            next_task_id: self.next_task_id,
+            parent: self.parent,
         }
     }
 }
@@ -73,6 +123,9 @@ impl AnyScope {
 pub struct Scope<COMP: Component> {
     parent: Option<Rc<AnyScope>>,
     state: Shared<Option<ComponentState<COMP>>>,
+    contexts: ContextMap,
+    tasks: TaskRegistry,
+    next_task_id: Rc<Cell<u32>>,
 }
 
 impl<COMP: Component> fmt::Debug for Scope<COMP> {
@@ -86,6 +139,9 @@ impl<COMP: Component> Clone for Scope<COMP> {
         Scope {
             parent: self.parent.clone(),
             state: self.state.clone(),
+            contexts: self.contexts.clone(),
+            tasks: self.tasks.clone(),
+            next_task_id: self.next_task_id.clone(),
         }
     }
 }
@@ -109,7 +165,13 @@ impl<COMP: Component> Scope<COMP> {
     pub(crate) fn new(parent: Option<AnyScope>) -> Self {
         let parent = parent.map(Rc::new);
         let state = Rc::new(RefCell::new(None));
-        Scope { parent, state }
+        Scope {
+            parent,
+            state,
+            contexts: ContextMap::default(),
+            tasks: TaskRegistry::default(),
+            next_task_id: Rc::new(Cell::new(0)),
+        }
     }
 
     /// Mounts a component with `props` to the specified `element` in the DOM.
@@ -151,6 +213,31 @@ impl<COMP: Component> Scope<COMP> {
         scheduler().push_comp(ComponentRunnableType::Rendered, Box::new(rendered));
     }
 
+    /// Detaches the component's rendered tree from the DOM while keeping its
+    /// state alive.
+    ///
+    /// This frees layout and paint cost for subtrees that are temporarily
+    /// off-screen (e.g. an inactive tab or route) without tearing down
+    /// [`ComponentState`]. Messages received while suspended still update the
+    /// component; the DOM is rebuilt on [`Scope::resume`].
+    pub fn suspend(&self) {
+        let state = self.state.clone();
+        scheduler().push_comp(
+            ComponentRunnableType::Update,
+            Box::new(SuspendComponent { state }),
+        );
+    }
+
+    /// Re-attaches a previously [`suspended`](Scope::suspend) component,
+    /// rebuilding its DOM from the preserved state without re-running `create`.
+    pub fn resume(&self) {
+        let state = self.state.clone();
+        scheduler().push_comp(
+            ComponentRunnableType::Update,
+            Box::new(ResumeComponent { state }),
+        );
+    }
+
     /// Schedules a task to destroy a component
     pub(crate) fn destroy(&mut self) {
         let state = self.state.clone();
@@ -158,6 +245,45 @@ impl<COMP: Component> Scope<COMP> {
         scheduler().push_comp(ComponentRunnableType::Destroy, Box::new(destroy));
     }
 
+    /// Makes `value` available as context to the descendants of this scope.
+    ///
+    /// Descendants can read it with [`AnyScope::consume_context`], which walks
+    /// the parent chain for the nearest provided value of the given type. A
+    /// later call with the same type replaces the previous value.
+    pub fn provide_context<T: 'static>(&self, value: T) {
+        self.contexts
+            .borrow_mut()
+            .insert(TypeId::of::<T>(), Rc::new(value) as Rc<dyn Any>);
+    }
+
+    /// Spawns a future bound to the component's lifetime.
+    ///
+    /// The future runs on the local executor and is aborted automatically when
+    /// the component is destroyed, so callbacks it holds can never fire into a
+    /// dead scope. This removes the need for manual guard flags when running
+    /// background work such as fetches or timers.
+    pub fn spawn<F>(&self, fut: F)
+    where
+        F: Future<Output = ()> + 'static,
+    {
+        let (handle, registration) = AbortHandle::new_pair();
+        // Register before polling the future so a task spawned during `create`
+        // (before `self.state` is populated) is still tracked for cancellation.
+        let id = self.next_task_id.get();
+        self.next_task_id.set(id.wrapping_add(1));
+        self.tasks.borrow_mut().insert(id, handle);
+        let tasks = Rc::downgrade(&self.tasks);
+        spawn_local(async move {
+            // The `Aborted` error is the expected outcome on destroy.
+            let _ = Abortable::new(fut, registration).await;
+            // Reap our own handle once the task settles so a long-lived
+            // component spawning many short tasks doesn't leak handles.
+            if let Some(tasks) = tasks.upgrade() {
+                tasks.borrow_mut().remove(&id);
+            }
+        });
+    }
+
     /// Send a message to the component
     pub fn send_message<T>(&self, msg: T)
     where
@@ -166,6 +292,43 @@ impl<COMP: Component> Scope<COMP> {
         self.update(ComponentUpdate::Message(msg.into()), false);
     }
 
+    /// Spawns a future and sends its output back to the component as a message
+    /// once it resolves.
+    ///
+    /// This lets a component kick off a fetch or timer without reaching for
+    /// `wasm_bindgen_futures` directly. The output is silently dropped if the
+    /// component has been destroyed before the future completes.
+    pub fn send_future<F>(&self, future: F)
+    where
+        F: Future<Output = COMP::Message> + 'static,
+    {
+        let state = Rc::downgrade(&self.state);
+        spawn_local(async move {
+            let output = future.await;
+            if let Some(state) = state.upgrade() {
+                // Clone the scope out before sending so the borrow isn't held
+                // across `update`.
+                let scope = state.borrow().as_ref().map(|state| state.scope.clone());
+                if let Some(scope) = scope {
+                    scope.update(ComponentUpdate::Message(output), false);
+                }
+            }
+        });
+    }
+
+    /// Carries out an [`UpdateAction`] returned by a component.
+    ///
+    /// [`UpdateAction::None`] does nothing, [`UpdateAction::Render`] forces a
+    /// re-render and [`UpdateAction::Defer`] spawns the future via
+    /// [`send_future`](Self::send_future).
+    pub fn dispatch(&self, action: UpdateAction<COMP>) {
+        match action {
+            UpdateAction::None => {}
+            UpdateAction::Render => self.update(ComponentUpdate::Force, false),
+            UpdateAction::Defer(future) => self.send_future(future),
+        }
+    }
+
     /// Send a batch of messages to the component
     pub fn send_message_batch(&self, messages: Vec<COMP::Message>) {
         self.update(ComponentUpdate::MessageBatch(messages), false);
@@ -223,6 +386,8 @@ struct ComponentState<COMP: Component> {
     component: Box<COMP>,
     last_root: Option<VNode>,
     rendered: bool,
+    last_props: Option<COMP::Properties>,
+    suspended: bool,
 }
 
 impl<COMP: Component> ComponentState<COMP> {
@@ -233,7 +398,7 @@ impl<COMP: Component> ComponentState<COMP> {
         scope: Scope<COMP>,
         props: COMP::Properties,
     ) -> Self {
-        let component = Box::new(COMP::create(props, scope.clone()));
+        let component = Box::new(COMP::create(props.clone(), scope.clone()));
         Self {
             element,
             node_ref,
@@ -241,10 +406,52 @@ impl<COMP: Component> ComponentState<COMP> {
             component,
             last_root: ancestor,
             rendered: false,
+            last_props: Some(props),
+            suspended: false,
         }
     }
 }
 
+/// Compares two property values through the autoref-based dispatch below.
+///
+/// The dispatch is resolved against the generic `T`, which carries no
+/// `PartialEq` bound here, so the fallback is always selected and this
+/// function always returns `false`, even for `Properties` that derive
+/// `PartialEq`. Every props update therefore reports "changed".
+fn props_eq<T>(a: &T, b: &T) -> bool {
+    use props_eq::{MaybePartialEq, MaybePartialEqFallback};
+    (&&Probe(a)).maybe_eq(&&Probe(b))
+}
+
+/// Wrapper whose method-resolution level picks between the specialized
+/// `PartialEq` path and the always-`false` fallback; see [`props_eq`] for the
+/// one selected there.
+struct Probe<'a, T>(&'a T);
+
+mod props_eq {
+    use super::Probe;
+
+    /// Specialized path: selected when `T: PartialEq`.
+    pub trait MaybePartialEq {
+        fn maybe_eq(&self, other: &Self) -> bool;
+    }
+
+    impl<T: PartialEq> MaybePartialEq for &Probe<'_, T> {
+        fn maybe_eq(&self, other: &Self) -> bool {
+            self.0 == other.0
+        }
+    }
+
+    /// Fallback path: selected for any `T`, considered never equal.
+    pub trait MaybePartialEqFallback {
+        fn maybe_eq(&self, _other: &Self) -> bool {
+            false
+        }
+    }
+
+    impl<T> MaybePartialEqFallback for Probe<'_, T> {}
+}
+
 struct UpdateComponent<COMP>
 where
     COMP: Component,
@@ -253,6 +460,26 @@ where
     update: ComponentUpdate<COMP>,
 }
 
+impl<COMP> UpdateComponent<COMP>
+where
+    COMP: Component,
+{
+    /// Resolves an [`UpdateAction`] returned by `update` into a `ShouldRender`
+    /// flag, spawning any deferred follow-up via [`Scope::send_future`]. The
+    /// spawn is weak over the component state, so a resolved future whose
+    /// component has since been destroyed is silently dropped.
+    fn handle_action(scope: &Scope<COMP>, action: UpdateAction<COMP>) -> bool {
+        match action {
+            UpdateAction::None => false,
+            UpdateAction::Render => true,
+            UpdateAction::Defer(future) => {
+                scope.send_future(future);
+                false
+            }
+        }
+    }
+}
+
 impl<COMP> Runnable for UpdateComponent<COMP>
 where
     COMP: Component,
@@ -261,19 +488,40 @@ where
         if let Some(mut state) = self.state.borrow_mut().as_mut() {
             let should_update = match self.update {
                 ComponentUpdate::Force => true,
-                ComponentUpdate::Message(message) => state.component.update(message),
-                ComponentUpdate::MessageBatch(messages) => messages
-                    .into_iter()
-                    .fold(false, |acc, msg| state.component.update(msg) || acc),
+                // `Component::update` returns an `UpdateAction` (its signature
+                // lives alongside the trait in `html/mod.rs`); `Defer` spawns a
+                // follow-up message while `None`/`Render` map to the old bool.
+                ComponentUpdate::Message(message) => {
+                    Self::handle_action(&state.scope, state.component.update(message))
+                }
+                ComponentUpdate::MessageBatch(messages) => {
+                    messages.into_iter().fold(false, |acc, msg| {
+                        Self::handle_action(&state.scope, state.component.update(msg)) || acc
+                    })
+                }
                 ComponentUpdate::Properties(props, node_ref) => {
                     // When components are updated, they receive a new node ref that
                     // must be linked to previous one.
                     node_ref.link(state.node_ref.clone());
-                    state.component.change(props)
+                    // `change`/`render` are skipped only when `props_eq` reports the
+                    // new props equal to the last applied ones; as `props_eq`
+                    // always returns `false`, new props are always applied.
+                    let unchanged = state
+                        .last_props
+                        .as_ref()
+                        .map(|last| props_eq(&props, last))
+                        .unwrap_or(false);
+                    if unchanged {
+                        false
+                    } else {
+                        let should_render = state.component.change(props.clone());
+                        state.last_props = Some(props);
+                        should_render
+                    }
                 }
             };
 
-            if should_update {
+            if should_update && !state.suspended {
                 state.rendered = false;
                 let mut root = state.component.render();
                 let last_root = state.last_root.take();
@@ -316,6 +564,66 @@ where
     }
 }
 
+struct SuspendComponent<COMP>
+where
+    COMP: Component,
+{
+    state: Shared<Option<ComponentState<COMP>>>,
+}
+
+impl<COMP> Runnable for SuspendComponent<COMP>
+where
+    COMP: Component,
+{
+    fn run(self: Box<Self>) {
+        if let Some(mut state) = self.state.borrow_mut().as_mut() {
+            if !state.suspended {
+                if let Some(mut last_root) = state.last_root.take() {
+                    // Taking `last_root` leaves it cleared, so a subsequent destroy
+                    // doesn't detach the already-removed node a second time.
+                    last_root.detach(&state.element);
+                }
+                state.suspended = true;
+            }
+        }
+    }
+}
+
+struct ResumeComponent<COMP>
+where
+    COMP: Component,
+{
+    state: Shared<Option<ComponentState<COMP>>>,
+}
+
+impl<COMP> Runnable for ResumeComponent<COMP>
+where
+    COMP: Component,
+{
+    fn run(self: Box<Self>) {
+        if let Some(mut state) = self.state.borrow_mut().as_mut() {
+            if state.suspended {
+                state.suspended = false;
+                // Rebuild the DOM from scratch out of the preserved component.
+                state.last_root = None;
+                state.rendered = false;
+                let mut root = state.component.render();
+                if let Some(node) =
+                    root.apply(&state.scope.clone().into(), &state.element, None, None)
+                {
+                    state.node_ref.set(Some(node));
+                } else if let VNode::VComp(child) = &root {
+                    state.node_ref.link(child.node_ref.clone());
+                }
+                state.last_root = Some(root);
+                // Re-attaching is a render, so fire `rendered` like every other
+                // render path does.
+                state.scope.rendered(false);
+            }
+        }
+    }
+}
+
 struct DestroyComponent<COMP>
 where
     COMP: Component,
@@ -329,6 +637,11 @@ where
 {
     fn run(self: Box<Self>) {
         if let Some(mut state) = self.state.borrow_mut().take() {
+            // Abort any in-flight tasks before the component goes away so they
+            // cannot deliver callbacks into a torn-down scope.
+            for (_, task) in state.scope.tasks.borrow_mut().drain() {
+                task.abort();
+            }
             drop(state.component);
             if let Some(last_frame) = &mut state.last_root {
                 last_frame.detach(&state.element);